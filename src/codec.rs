@@ -0,0 +1,39 @@
+use anyhow::{Context, Result};
+use serde::{de::DeserializeOwned, Serialize};
+
+/// 值序列化编解码器
+///
+/// `ColumnFamily`默认使用`JsonCodec`以保持向后兼容；对存储大小、CPU开销敏感的
+/// 列族可以切换到`BincodeCodec`等更紧凑的二进制编码，做法类似Solana
+/// blockstore对账本数据使用的bincode编码
+pub trait Codec {
+    fn encode<T: Serialize>(value: &T) -> Result<Vec<u8>>;
+    fn decode<T: DeserializeOwned>(bytes: &[u8]) -> Result<T>;
+}
+
+/// 默认编解码器：基于`serde_json`，人类可读、方便调试，和现有存储数据保持兼容
+pub struct JsonCodec;
+
+impl Codec for JsonCodec {
+    fn encode<T: Serialize>(value: &T) -> Result<Vec<u8>> {
+        serde_json::to_vec(value).context("Failed to serialize value")
+    }
+
+    fn decode<T: DeserializeOwned>(bytes: &[u8]) -> Result<T> {
+        serde_json::from_slice(bytes).context("Failed to deserialize value")
+    }
+}
+
+/// 基于`bincode`的二进制编解码器：比`JsonCodec`更紧凑、编解码更快，
+/// 适合性能敏感、schema相对稳定的列族
+pub struct BincodeCodec;
+
+impl Codec for BincodeCodec {
+    fn encode<T: Serialize>(value: &T) -> Result<Vec<u8>> {
+        bincode::serialize(value).context("Failed to serialize value")
+    }
+
+    fn decode<T: DeserializeOwned>(bytes: &[u8]) -> Result<T> {
+        bincode::deserialize(bytes).context("Failed to deserialize value")
+    }
+}
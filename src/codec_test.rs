@@ -0,0 +1,71 @@
+use crate::{BincodeCodec, Codec, ColumnFamily, DbContext, JsonCodec, Keyable};
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use tempfile::TempDir;
+
+#[derive(Serialize, Deserialize, PartialEq, Eq, Debug, Clone)]
+struct CodecSample {
+    id: String,
+    name: String,
+    count: u64,
+}
+
+impl Keyable for CodecSample {
+    fn key(&self) -> String {
+        self.id.clone()
+    }
+
+    fn column_family() -> &'static str {
+        "codec_samples"
+    }
+}
+
+#[test]
+fn test_bincode_codec_round_trip() -> Result<()> {
+    let value = CodecSample {
+        id: "001".to_string(),
+        name: "Alice".to_string(),
+        count: 42,
+    };
+
+    let bytes = BincodeCodec::encode(&value)?;
+    let decoded: CodecSample = BincodeCodec::decode(&bytes)?;
+    assert_eq!(decoded, value);
+
+    Ok(())
+}
+
+#[test]
+fn test_json_codec_round_trip() -> Result<()> {
+    let value = CodecSample {
+        id: "002".to_string(),
+        name: "Bob".to_string(),
+        count: 7,
+    };
+
+    let bytes = JsonCodec::encode(&value)?;
+    let decoded: CodecSample = JsonCodec::decode(&bytes)?;
+    assert_eq!(decoded, value);
+
+    Ok(())
+}
+
+#[test]
+fn test_column_family_with_bincode_codec_persists_and_reads_back() -> Result<()> {
+    let temp_dir = TempDir::new().expect("Failed to create temp directory");
+    let column_families = vec!["codec_samples"];
+    let db = DbContext::open(temp_dir.path(), &column_families)?;
+
+    let cf = ColumnFamily::<CodecSample, BincodeCodec>::new(db);
+
+    let value = CodecSample {
+        id: "003".to_string(),
+        name: "Carol".to_string(),
+        count: 99,
+    };
+    cf.set(&value)?;
+
+    assert_eq!(cf.get(&"003".to_string())?.unwrap(), value);
+
+    Ok(())
+}
@@ -1,82 +1,298 @@
+use crate::codec::{Codec, JsonCodec};
 use crate::DbContext;
 use anyhow::{Context, Result};
-use rocksdb::IteratorMode;
+use byteorder::{BigEndian, WriteBytesExt};
+use rocksdb::{DBIteratorWithThreadMode, Direction, IteratorMode, TransactionDB};
 use serde::{de::DeserializeOwned, Serialize};
+use std::sync::Arc;
+use std::time::Duration;
 
-fn serialize_to_bytes<T: Serialize>(value: &T) -> Result<Vec<u8>> {
-    serde_json::to_vec(value).context("Failed to serialize value")
+/// 旧版的 String 主键特性，保留以兼容现有实现
+///
+/// 任何实现了`Keyable`的类型都会通过下方的blanket impl自动获得`Column`能力，
+/// 主键按UTF-8编解码，行为与之前完全一致
+pub trait Keyable: Serialize + DeserializeOwned {
+    fn key(&self) -> String;
+    fn column_family() -> &'static str;
 }
 
-fn deserialize_from_bytes<T: DeserializeOwned>(bytes: &[u8]) -> Result<T> {
-    serde_json::from_slice(bytes).context("Failed to deserialize value")
-}
+/// 借鉴Solana `blockstore_db`的`Column`设计：允许类型声明自己的主键类型，
+/// 以及该主键与有序字节数组之间的编解码方式，从而摆脱字符串比较带来的排序限制
+/// (比如`generate_timestamp_index`为了维持字典序不得不对数字做20位零填充)
+pub trait Column: Serialize + DeserializeOwned {
+    type Key;
 
-pub trait Keyable: serde::Serialize + serde::de::DeserializeOwned {
-    fn key(&self) -> String;
+    fn key(&self) -> Self::Key;
     fn column_family() -> &'static str;
+
+    /// 将主键编码为保持有序比较语义的字节数组
+    fn encode_key(key: &Self::Key) -> Vec<u8>;
+    /// 将字节数组还原为主键
+    fn decode_key(bytes: &[u8]) -> Result<Self::Key>;
+}
+
+/// 为兼容现有的String主键类型提供的默认实现：按UTF-8编解码
+impl<T: Keyable> Column for T {
+    type Key = String;
+
+    fn key(&self) -> String {
+        Keyable::key(self)
+    }
+
+    fn column_family() -> &'static str {
+        <T as Keyable>::column_family()
+    }
+
+    fn encode_key(key: &String) -> Vec<u8> {
+        key.as_bytes().to_vec()
+    }
+
+    fn decode_key(bytes: &[u8]) -> Result<String> {
+        String::from_utf8(bytes.to_vec()).context("Invalid UTF-8 in key")
+    }
+}
+
+/// 编码`(u64, &str)`形式的复合主键
+///
+/// 数值前缀采用大端序(`BigEndian`)写入，使得字节序与数值大小一致；字符串后缀原样追加。
+/// 这样无需像`generate_timestamp_index`那样对数字做字符串零填充，也能保持正确的排序。
+pub fn encode_composite_key(prefix: u64, suffix: &str) -> Vec<u8> {
+    let mut bytes = Vec::with_capacity(8 + suffix.len());
+    bytes
+        .write_u64::<BigEndian>(prefix)
+        .expect("writing to a Vec<u8> cannot fail");
+    bytes.extend_from_slice(suffix.as_bytes());
+    bytes
+}
+
+/// 描述打开某个列族时应使用的RocksDB选项
+///
+/// 通过`ColumnFamily::set_ttl`/`ColumnFamily::set_fifo_max_table_files_size`构造，
+/// 可以链式组合，最终传给`DbContext::initialize_with_options`，
+/// 由其在打开数据库时为对应列族注册TTL compaction filter以及/或FIFO compaction
+pub struct ColumnFamilyConfig {
+    pub name: &'static str,
+    pub ttl: Option<Duration>,
+    pub fifo_max_table_files_size: Option<u64>,
+}
+
+impl ColumnFamilyConfig {
+    fn for_column_family(name: &'static str) -> Self {
+        Self {
+            name,
+            ttl: None,
+            fifo_max_table_files_size: None,
+        }
+    }
+
+    /// 链式追加TTL配置
+    pub fn with_ttl(mut self, ttl: Duration) -> Self {
+        self.ttl = Some(ttl);
+        self
+    }
+
+    /// 链式追加FIFO compaction的总SST大小上限(字节)
+    pub fn with_fifo_max_table_files_size(mut self, max_bytes: u64) -> Self {
+        self.fifo_max_table_files_size = Some(max_bytes);
+        self
+    }
 }
 
 /// 表示RocksDB中的一个列族(column family)
 ///
-/// 泛型参数`T`需要实现`Keyable`特性，用于定义:
-/// 1. 数据的主键生成方式(`key()`)
+/// 泛型参数`T`需要实现`Column`特性，用于定义:
+/// 1. 主键类型及其有序字节编解码方式(`Key`、`encode_key`/`decode_key`)
 /// 2. 所属列族名称(`column_family()`)
 ///
+/// 泛型参数`C`是值的编解码器(`Codec`)，默认为`JsonCodec`以保持向后兼容；
+/// 性能敏感的列族可以指定`BincodeCodec`等更紧凑的编码
+///
 /// 提供基本的CRUD操作接口，包括：
 /// - 获取所有记录(`get_all`)
 /// - 按主键查询(`get`)
 /// - 删除记录(`del`)
 /// - 插入/更新记录(`set`)
 /// - 记录总数统计(`count_all`)
-pub struct ColumnFamily<T: Keyable> {
-    _phantom: std::marker::PhantomData<T>,
+pub struct ColumnFamily<T, C = JsonCodec> {
+    db: Arc<DbContext>,
+    _phantom: std::marker::PhantomData<(T, C)>,
+}
+
+/// 由`iter`产生的惰性流式迭代器(`get_all`也在其上做`collect`)
+///
+/// 按需对底层`DBIterator`的每一项做一次反序列化，而不是像`get_all`那样
+/// 预先把整个列族收集进一个`Vec`，因而可以在记录数很大时以有界内存处理，
+/// 并且允许调用方提前`break`掉迭代。反序列化失败只影响当前这一项(`Err`)，
+/// 不会中断整次扫描
+pub struct ColumnFamilyIter<'a, T, C = JsonCodec> {
+    inner: DBIteratorWithThreadMode<'a, TransactionDB>,
+    _phantom: std::marker::PhantomData<(T, C)>,
+}
+
+impl<'a, T: Column, C: Codec> Iterator for ColumnFamilyIter<'a, T, C> {
+    type Item = Result<T>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let (_key, value) = match self.inner.next()? {
+            Ok(entry) => entry,
+            Err(err) => return Some(Err(err).context("Failed to read database entry")),
+        };
+        Some(C::decode(&value))
+    }
+}
+
+/// 与`ColumnFamilyIter`相同，但只在`[start_key, end_key]`范围内迭代，
+/// 由`ColumnFamily::iter_range`基于`IteratorMode::From`的有界seek构造，
+/// 一旦遇到超出`end_key`的记录就提前结束，不会继续扫描到列族末尾
+pub struct ColumnFamilyRangeIter<'a, T, C = JsonCodec> {
+    inner: DBIteratorWithThreadMode<'a, TransactionDB>,
+    end_key: Vec<u8>,
+    done: bool,
+    _phantom: std::marker::PhantomData<(T, C)>,
+}
+
+impl<'a, T: Column, C: Codec> Iterator for ColumnFamilyRangeIter<'a, T, C> {
+    type Item = Result<T>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+
+        let (key, value) = match self.inner.next()? {
+            Ok(entry) => entry,
+            Err(err) => {
+                self.done = true;
+                return Some(Err(err).context("Failed to read database entry"));
+            }
+        };
+
+        if key.as_ref() > self.end_key.as_slice() {
+            self.done = true;
+            return None;
+        }
+
+        Some(C::decode(&value))
+    }
 }
 
-impl<T: Keyable> Default for ColumnFamily<T> {
+impl<T: Column, C: Codec> Default for ColumnFamily<T, C> {
+    /// 使用`DbContext::get_instance()`提供的默认单例句柄构造
+    ///
+    /// 只是`Self::new(DbContext::get_instance())`的一层便捷封装；如果进程里打开了
+    /// 多个独立的数据库，应改用`Self::new`显式传入对应的句柄
     fn default() -> Self {
-        Self::new()
+        Self::new(DbContext::get_instance())
     }
 }
 
-impl<T: Keyable> ColumnFamily<T> {
-    /// 创建指定类型的列族实例
+impl<T: Column, C: Codec> ColumnFamily<T, C> {
+    /// 创建指定类型的列族实例，绑定到显式传入的数据库句柄上
     ///
-    /// 该实例不包含实际数据，仅作为操作指定列族的接口
-    pub fn new() -> Self {
+    /// 该实例不包含实际数据，仅作为操作`db`中指定列族的接口。传入不同的`db`即可
+    /// 在同一进程中操作多个相互独立的数据库(例如一个缓存库和一个主库)
+    pub fn new(db: Arc<DbContext>) -> Self {
         Self {
+            db,
             _phantom: std::marker::PhantomData,
         }
     }
 
-    /// 获取当前列族中的所有记录
+    /// 为该列族声明一个TTL(存活时间)
+    ///
+    /// 返回的`ColumnFamilyConfig`需要传给`DbContext::initialize_with_options`，
+    /// 由其在打开数据库时为这个列族注册一个在后台compaction期间自动丢弃过期记录的
+    /// compaction filter，记录的写入时间从`generate_timestamp_index`产生的反转时间戳主键中解码。
+    /// 不同实体类型可以各自声明不同的保留窗口。
+    pub fn set_ttl(ttl: Duration) -> ColumnFamilyConfig {
+        ColumnFamilyConfig::for_column_family(T::column_family()).with_ttl(ttl)
+    }
+
+    /// 为该列族启用FIFO compaction
+    ///
+    /// 一旦列族中所有SST文件的总大小超过`max_bytes`，RocksDB会在后台compaction中
+    /// 自动丢弃最旧的SST文件，从而以O(1)的方式维持一个按字节数上限的环形缓冲区，
+    /// 不需要像`keep_size`那样做全量迭代。适合只关心"总大小不超过多少"而不关心
+    /// 精确记录数的列族；需要精确记录数上限的调用方应继续使用`keep_size`。
+    pub fn set_fifo_max_table_files_size(max_bytes: u64) -> ColumnFamilyConfig {
+        ColumnFamilyConfig::for_column_family(T::column_family())
+            .with_fifo_max_table_files_size(max_bytes)
+    }
+
+    /// 以惰性流式的方式迭代当前列族中的所有记录
+    ///
+    /// 每次`next()`只反序列化一条记录，不会像`get_all`那样预先把整个列族
+    /// 读进内存，适合记录数很大、或者调用方希望提前终止扫描的场景
     ///
     /// # 返回值
-    /// - `Ok(Vec<T>)`: 包含所有反序列化后的记录
-    /// - `Err`: 当发生以下情况时返回错误：
-    ///   - 无法获取列族句柄
-    ///   - 数据库迭代失败
-    ///   - 数据反序列化失败
-    pub fn get_all(&self) -> Result<Vec<T>> {
-        let cf_handle = DbContext::get_instance()
-            .db
+    /// - `Ok(ColumnFamilyIter<T>)`: 可迭代、逐项产出`Result<T>`的流
+    /// - `Err`: 无法获取列族句柄
+    pub fn iter(&self) -> Result<ColumnFamilyIter<'_, T, C>> {
+        let cf_handle = self.db.db
             .cf_handle(T::column_family())
             .context(format!(
                 "Failed to get {} column family handle",
                 T::column_family()
             ))?;
 
-        let mut items = Vec::new();
-        let iter = DbContext::get_instance()
-            .db
-            .iterator_cf(&cf_handle, IteratorMode::Start);
+        let inner = self.db.db.iterator_cf(&cf_handle, IteratorMode::Start);
 
-        for item in iter {
-            let (_key, value) = item.context("Failed to read database entry")?;
-            let item: T = deserialize_from_bytes(&value)?;
-            items.push(item);
-        }
+        Ok(ColumnFamilyIter {
+            inner,
+            _phantom: std::marker::PhantomData,
+        })
+    }
 
-        Ok(items)
+    /// 以惰性流式的方式迭代`[start_key, end_key]`范围内的记录
+    ///
+    /// 和`iter()`一样按需反序列化，但直接从`start_key`对应的字节位置`seek`进去
+    /// (`IteratorMode::From` + `Direction::Forward`)，一旦遇到超出`end_key`的
+    /// 记录就提前结束，而不是扫描完整个列族
+    ///
+    /// # 返回值
+    /// - `Ok(ColumnFamilyRangeIter<T>)`: 可迭代、逐项产出`Result<T>`的流
+    /// - `Err`: 无法获取列族句柄
+    pub fn iter_range(
+        &self,
+        start_key: &T::Key,
+        end_key: &T::Key,
+    ) -> Result<ColumnFamilyRangeIter<'_, T, C>> {
+        let cf_handle = self.db.db
+            .cf_handle(T::column_family())
+            .context(format!(
+                "Failed to get {} column family handle",
+                T::column_family()
+            ))?;
+
+        let start_bytes = T::encode_key(start_key);
+        let end_bytes = T::encode_key(end_key);
+
+        let inner = self.db.db.iterator_cf(
+            &cf_handle,
+            IteratorMode::From(&start_bytes, Direction::Forward),
+        );
+
+        Ok(ColumnFamilyRangeIter {
+            inner,
+            end_key: end_bytes,
+            done: false,
+            _phantom: std::marker::PhantomData,
+        })
+    }
+
+    /// 获取当前列族中的所有记录
+    ///
+    /// 是`iter().collect()`的一层薄封装，保留用于向后兼容；新代码在记录数较多时
+    /// 应优先使用`iter()`以避免一次性把整个列族读进内存
+    ///
+    /// # 返回值
+    /// - `Ok(Vec<T>)`: 包含所有反序列化后的记录
+    /// - `Err`: 当发生以下情况时返回错误：
+    ///   - 无法获取列族句柄
+    ///   - 数据库迭代失败
+    ///   - 数据反序列化失败
+    pub fn get_all(&self) -> Result<Vec<T>> {
+        self.iter()?.collect()
     }
 
     /// 根据主键查询单条记录
@@ -91,22 +307,20 @@ impl<T: Keyable> ColumnFamily<T> {
     ///   - 无法获取列族句柄
     ///   - 数据库读取失败
     ///   - 数据反序列化失败
-    pub fn get(&self, key: &str) -> Result<Option<T>> {
-        let cf_handle = DbContext::get_instance()
-            .db
+    pub fn get(&self, key: &T::Key) -> Result<Option<T>> {
+        let cf_handle = self.db.db
             .cf_handle(T::column_family())
             .context(format!(
                 "Failed to get {} column family handle",
                 T::column_family()
             ))?;
 
-        match DbContext::get_instance()
-            .db
-            .get_cf(&cf_handle, key)
+        match self.db.db
+            .get_cf(&cf_handle, T::encode_key(key))
             .context("Failed to read database entry")?
         {
             Some(value) => {
-                let item: T = deserialize_from_bytes(&value)?;
+                let item: T = C::decode(&value)?;
                 Ok(Some(item))
             }
             None => Ok(None),
@@ -123,18 +337,16 @@ impl<T: Keyable> ColumnFamily<T> {
     /// - `Err`: 当发生以下情况时返回错误：
     ///   - 无法获取列族句柄
     ///   - 数据库删除操作失败
-    pub fn del(&self, key: &str) -> Result<()> {
-        let cf_handle = DbContext::get_instance()
-            .db
+    pub fn del(&self, key: &T::Key) -> Result<()> {
+        let cf_handle = self.db.db
             .cf_handle(T::column_family())
             .context(format!(
                 "Failed to get {} column family handle",
                 T::column_family()
             ))?;
 
-        DbContext::get_instance()
-            .db
-            .delete_cf(&cf_handle, key)
+        self.db.db
+            .delete_cf(&cf_handle, T::encode_key(key))
             .context("Failed to delete item")
     }
 
@@ -152,19 +364,17 @@ impl<T: Keyable> ColumnFamily<T> {
     ///   - 数据序列化失败
     ///   - 数据库写入失败
     pub fn set(&self, item: &T) -> Result<()> {
-        let cf_handle = DbContext::get_instance()
-            .db
+        let cf_handle = self.db.db
             .cf_handle(T::column_family())
             .context(format!(
                 "Failed to get {} column family handle",
                 T::column_family()
             ))?;
 
-        let key = item.key();
-        let value = serialize_to_bytes(item)?;
+        let key = T::encode_key(&Column::key(item));
+        let value = C::encode(item)?;
 
-        DbContext::get_instance()
-            .db
+        self.db.db
             .put_cf(&cf_handle, key, value)
             .context("Failed to write item to database")
     }
@@ -177,17 +387,14 @@ impl<T: Keyable> ColumnFamily<T> {
     ///   - 无法获取列族句柄
     ///   - 数据库迭代失败
     pub fn count_all(&self) -> Result<usize> {
-        let cf_handle = DbContext::get_instance()
-            .db
+        let cf_handle = self.db.db
             .cf_handle(T::column_family())
             .context(format!(
                 "Failed to get {} column family handle",
                 T::column_family()
             ))?;
 
-        let iter = DbContext::get_instance()
-            .db
-            .iterator_cf(&cf_handle, IteratorMode::Start);
+        let iter = self.db.db.iterator_cf(&cf_handle, IteratorMode::Start);
 
         let mut count = 0;
         for item in iter {
@@ -200,7 +407,11 @@ impl<T: Keyable> ColumnFamily<T> {
 
     /// 保持列族大小不超过指定限制
     ///
-    /// 如果当前记录数超过限制，则删除最早的数据(按照RocksDB索引顺序)
+    /// 如果当前记录数超过限制，则删除最早的数据(按照RocksDB索引顺序)。这需要一次
+    /// `count_all`全量迭代加一次构建删除批次的迭代，只适合记录数较小的列族，或者
+    /// 调用方确实需要"精确记录数上限"而非"总字节数上限"的场景；只关心总字节数的
+    /// 列族应改用`ColumnFamily::set_fifo_max_table_files_size`让RocksDB在后台
+    /// compaction中自动、以O(1)的方式丢弃最旧的数据。
     ///
     /// # 参数
     /// - `size`: 允许的最大记录数
@@ -216,16 +427,17 @@ impl<T: Keyable> ColumnFamily<T> {
             return Ok(());
         }
 
-        let cf_handle = DbContext::get_instance()
-            .db
+        let cf_handle = self.db.db
             .cf_handle(T::column_family())
             .context(format!(
                 "Failed to get {} column family handle",
                 T::column_family()
             ))?;
 
-        let db = &DbContext::get_instance().db;
-        let mut batch = rocksdb::WriteBatch::default();
+        let db = &self.db.db;
+        // `TransactionDB::write`只接受`WriteBatchWithTransaction<true>`，
+        // 和普通`DB`使用的`WriteBatch`(`WriteBatchWithTransaction<false>`)不是同一个类型
+        let mut batch = rocksdb::WriteBatchWithTransaction::<true>::default();
         let iter = db.iterator_cf(&cf_handle, IteratorMode::Start);
 
         let mut keys_to_delete = Vec::new();
@@ -249,10 +461,14 @@ impl<T: Keyable> ColumnFamily<T> {
 
         Ok(())
     }
+}
 
+impl<T: Keyable, C: Codec> ColumnFamily<T, C> {
     /// 根据时间索引范围过滤数据
     ///
-    /// 假设数据是按照时间索引键值排序的
+    /// 假设数据是按照`generate_timestamp_index`产生的反转时间戳升序排列的，
+    /// 因此可以直接从区间起点`seek`进去，而不必从头扫描整个列族：只需定位到
+    /// `start_key`，正向迭代，一旦遇到超出`end_key`的记录就提前结束。
     ///
     /// # 参数
     /// - `start_time`: 起始时间戳(包含)
@@ -265,8 +481,7 @@ impl<T: Keyable> ColumnFamily<T> {
     ///   - 数据库迭代失败
     ///   - 数据反序列化失败
     pub fn filter_by_time_index(&self, start_time: u64, end_time: u64) -> Result<Vec<T>> {
-        let cf_handle = DbContext::get_instance()
-            .db
+        let cf_handle = self.db.db
             .cf_handle(T::column_family())
             .context(format!(
                 "Failed to get {} column family handle",
@@ -274,25 +489,37 @@ impl<T: Keyable> ColumnFamily<T> {
             ))?;
 
         let max_timestamp = i64::MAX as u64;
-        let start_key = (max_timestamp - end_time).to_string();
-        let end_key = (max_timestamp - start_time).to_string();
+        // generate_timestamp_index把反转时间戳编码成20位零填充的十进制前缀，
+        // 这里必须用同样的宽度编码区间端点，否则字节序比较会错位(例如未填充的
+        // "92233..."在字典序上反而排在填充后的"092233..."之后，导致seek直接
+        // 越过所有记录)
+        let start_key = format!("{:020}", max_timestamp - end_time);
+        let end_key = format!("{:020}", max_timestamp - start_time);
 
         let mut items = Vec::new();
-        let iter = DbContext::get_instance()
-            .db
-            .iterator_cf(&cf_handle, IteratorMode::Start);
+        let iter = self.db.db.iterator_cf(
+            &cf_handle,
+            IteratorMode::From(start_key.as_bytes(), Direction::Forward),
+        );
 
         for item in iter {
             let (key, value) = item.context("Failed to read database entry")?;
             let key_str = String::from_utf8(key.to_vec()).context("Invalid UTF-8 in key")?;
-            
-            // 检查key是否在范围内
-            if key_str >= start_key && key_str <= end_key {
-                let item: T = deserialize_from_bytes(&value)?;
+            // 键的格式是"{inverted_timestamp:020}_{config_id}"，范围比较只看前20位的
+            // 时间戳部分，避免把后缀`config_id`也卷入字符串比较
+            let key_prefix = key_str.get(..20).unwrap_or(key_str.as_str());
+
+            if key_prefix > end_key.as_str() {
+                break;
+            }
+
+            // seek之后产出的键都不小于start_key，这里的下界校验只是防御性的再确认一次
+            if key_prefix >= start_key.as_str() {
+                let item: T = C::decode(&value)?;
                 items.push(item);
             }
         }
 
         Ok(items)
     }
-}
\ No newline at end of file
+}
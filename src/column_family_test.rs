@@ -1,8 +1,8 @@
 use super::*;
-use crate::{ColumnFamily, DbContext, Keyable};
+use crate::{encode_composite_key, generate_timestamp_index, ColumnFamily, DbContext, Keyable};
 use anyhow::Result;
+use chrono::{TimeZone, Utc};
 use serde::{Deserialize, Serialize};
-use std::sync::OnceLock;
 use tempfile::TempDir;
 
 #[derive(Serialize, Deserialize, PartialEq, Eq, Debug, Clone)]
@@ -21,22 +21,15 @@ impl Keyable for TestUser {
     }
 }
 
-// 模块级共享临时目录对象
-static GLOBAL_TEMP_DIR: OnceLock<TempDir> = OnceLock::new();
-
-fn get_test_tempdir() -> &'static TempDir {
-    GLOBAL_TEMP_DIR.get_or_init(|| TempDir::new().expect("Failed to create global temp directory"))
-}
-
 #[test]
 fn test_crud_operations() -> Result<()> {
-    let global_temp = get_test_tempdir();
-    let db_path = global_temp.path();
+    let temp_dir = TempDir::new().expect("Failed to create temp directory");
     let column_families = vec!["test_users"];
 
-    DbContext::initialize(db_path, &column_families)?;
+    // 每个测试打开自己独立的数据库实例，不再需要共享一个全局临时目录
+    let db = DbContext::open(temp_dir.path(), &column_families)?;
 
-    let user_cf = ColumnFamily::<TestUser>::new();
+    let user_cf = ColumnFamily::<TestUser>::new(db);
 
     // Create
     let user1 = TestUser {
@@ -46,7 +39,7 @@ fn test_crud_operations() -> Result<()> {
     user_cf.set(&user1)?;
 
     // Read single
-    let retrieved = user_cf.get("001")?.unwrap();
+    let retrieved = user_cf.get(&"001".to_string())?.unwrap();
     assert_eq!(retrieved.name, "Alice");
 
     // Read all
@@ -70,18 +63,137 @@ fn test_crud_operations() -> Result<()> {
         name: "Alicia".to_string(),
     };
     user_cf.set(&updated_user)?;
-    assert_eq!(user_cf.get("001")?.unwrap().name, "Alicia");
+    assert_eq!(user_cf.get(&"001".to_string())?.unwrap().name, "Alicia");
 
     // Delete
-    user_cf.del("001")?;
-    assert!(user_cf.get("001")?.is_none());
+    user_cf.del(&"001".to_string())?;
+    assert!(user_cf.get(&"001".to_string())?.is_none());
 
     // Verify remaining data
     let remaining = user_cf.get_all()?;
     assert_eq!(remaining.len(), 1);
     assert_eq!(remaining[0], user2);
 
-    assert!(user_cf.get("non_existent")?.is_none());
+    assert!(user_cf.get(&"non_existent".to_string())?.is_none());
+
+    Ok(())
+}
+
+#[test]
+fn test_encode_composite_key_preserves_numeric_order() {
+    // 前缀按大端序写入，字节序应当和数值大小完全一致，包括跨越u32边界的情况
+    let low = encode_composite_key(1, "suffix");
+    let high = encode_composite_key(2, "suffix");
+    let far = encode_composite_key(u32::MAX as u64 + 1, "suffix");
+    assert!(low < high);
+    assert!(high < far);
+
+    // 相同前缀下，后缀按原始字节顺序追加比较
+    let a = encode_composite_key(42, "a");
+    let b = encode_composite_key(42, "b");
+    assert!(a < b);
+}
+
+#[test]
+fn test_multiple_independent_db_instances_stay_isolated() -> Result<()> {
+    // 模拟一个缓存库和一个主库：两个`DbContext`各自打开在独立的临时目录上，
+    // 写入同一个主键不应互相影响
+    let cache_dir = TempDir::new().expect("Failed to create temp directory");
+    let primary_dir = TempDir::new().expect("Failed to create temp directory");
+    let column_families = vec!["test_users"];
+
+    let cache_db = DbContext::open(cache_dir.path(), &column_families)?;
+    let primary_db = DbContext::open(primary_dir.path(), &column_families)?;
+
+    let cache_cf = ColumnFamily::<TestUser>::new(cache_db);
+    let primary_cf = ColumnFamily::<TestUser>::new(primary_db);
+
+    cache_cf.set(&TestUser {
+        id: "001".to_string(),
+        name: "CacheOnly".to_string(),
+    })?;
+    primary_cf.set(&TestUser {
+        id: "001".to_string(),
+        name: "PrimaryOnly".to_string(),
+    })?;
+
+    assert_eq!(cache_cf.get(&"001".to_string())?.unwrap().name, "CacheOnly");
+    assert_eq!(
+        primary_cf.get(&"001".to_string())?.unwrap().name,
+        "PrimaryOnly"
+    );
+    assert_eq!(cache_cf.count_all()?, 1);
+    assert_eq!(primary_cf.count_all()?, 1);
+
+    Ok(())
+}
+
+#[derive(Serialize, Deserialize, PartialEq, Eq, Debug, Clone)]
+struct TimeIndexedRecord {
+    key: String,
+    label: String,
+}
+
+impl Keyable for TimeIndexedRecord {
+    fn key(&self) -> String {
+        self.key.clone()
+    }
+
+    fn column_family() -> &'static str {
+        "time_indexed_records"
+    }
+}
+
+#[test]
+fn test_filter_by_time_index_returns_exact_window() -> Result<()> {
+    let temp_dir = TempDir::new().expect("Failed to create temp directory");
+    let column_families = vec!["time_indexed_records"];
+    let db = DbContext::open(temp_dir.path(), &column_families)?;
+    let cf = ColumnFamily::<TimeIndexedRecord>::new(db);
+
+    for &ts in &[1_000_u64, 2_000, 3_000, 4_000] {
+        let key = generate_timestamp_index("cfg", Utc.timestamp_opt(ts as i64, 0).unwrap());
+        cf.set(&TimeIndexedRecord {
+            key,
+            label: format!("record_{}", ts),
+        })?;
+    }
+
+    let in_window = cf.filter_by_time_index(2_000, 3_000)?;
+    let mut labels: Vec<String> = in_window.iter().map(|r| r.label.clone()).collect();
+    labels.sort();
+    assert_eq!(
+        labels,
+        vec!["record_2000".to_string(), "record_3000".to_string()]
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_iter_range_returns_bounded_window() -> Result<()> {
+    let temp_dir = TempDir::new().expect("Failed to create temp directory");
+    let column_families = vec!["test_users"];
+    let db = DbContext::open(temp_dir.path(), &column_families)?;
+    let cf = ColumnFamily::<TestUser>::new(db);
+
+    for id in ["001", "002", "003", "004", "005"] {
+        cf.set(&TestUser {
+            id: id.to_string(),
+            name: format!("user_{}", id),
+        })?;
+    }
+
+    let windowed = cf
+        .iter_range(&"002".to_string(), &"004".to_string())?
+        .collect::<Result<Vec<_>>>()?;
+
+    let mut ids: Vec<String> = windowed.into_iter().map(|u| u.id).collect();
+    ids.sort();
+    assert_eq!(
+        ids,
+        vec!["002".to_string(), "003".to_string(), "004".to_string()]
+    );
 
     Ok(())
 }
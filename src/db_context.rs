@@ -1,34 +1,143 @@
+use crate::codec::Codec;
+use crate::column_family::ColumnFamilyConfig;
+use crate::ttl::{system_clock, TtlCompactionFilterFactory};
+use crate::txn::Txn;
 use anyhow::{Context, Result};
-use rocksdb::{Options, DB};
+use rocksdb::{
+    ColumnFamilyDescriptor, DBCompactionStyle, FifoCompactOptions, Options, TransactionDB,
+    TransactionDBOptions,
+};
 use std::path::Path;
-use std::sync::OnceLock; // 移除了Arc的引入
+use std::sync::{Arc, OnceLock};
 
-fn open_db_with_column_families(db_path: &Path, column_families: &[&str]) -> Result<DB> {
+fn open_db_with_column_families(
+    db_path: &Path,
+    column_families: &[&str],
+) -> Result<TransactionDB> {
     std::fs::create_dir_all(db_path).context("Failed to create db directory")?;
 
     let mut opts = Options::default();
     opts.create_if_missing(true);
     opts.create_missing_column_families(true);
+    let txn_db_opts = TransactionDBOptions::default();
 
-    DB::open_cf(&opts, db_path, column_families)
+    TransactionDB::open_cf(&opts, &txn_db_opts, db_path, column_families)
         .context("Failed to open RocksDB with column families")
 }
 
+/// 和`open_db_with_column_families`类似，但为每个列族分别应用`ColumnFamilyConfig`里
+/// 声明的选项(目前是TTL compaction filter、FIFO compaction)，而不是对所有列族使用
+/// 同一份默认`Options`
+fn open_db_with_column_family_configs(
+    db_path: &Path,
+    column_families: &[ColumnFamilyConfig],
+) -> Result<TransactionDB> {
+    std::fs::create_dir_all(db_path).context("Failed to create db directory")?;
+
+    let mut db_opts = Options::default();
+    db_opts.create_if_missing(true);
+    db_opts.create_missing_column_families(true);
+    let txn_db_opts = TransactionDBOptions::default();
+
+    let descriptors = column_families
+        .iter()
+        .map(|cf| {
+            let mut cf_opts = Options::default();
+            if let Some(ttl) = cf.ttl {
+                cf_opts.set_compaction_filter_factory(TtlCompactionFilterFactory::new(
+                    ttl,
+                    system_clock,
+                ));
+            }
+            if let Some(max_bytes) = cf.fifo_max_table_files_size {
+                cf_opts.set_compaction_style(DBCompactionStyle::Fifo);
+                let mut fifo_opts = FifoCompactOptions::default();
+                fifo_opts.set_max_table_files_size(max_bytes);
+                cf_opts.set_fifo_compaction_options(&fifo_opts);
+            }
+            ColumnFamilyDescriptor::new(cf.name, cf_opts)
+        })
+        .collect::<Vec<_>>();
+
+    TransactionDB::open_cf_descriptors(&db_opts, &txn_db_opts, db_path, descriptors)
+        .context("Failed to open RocksDB with column family configs")
+}
+
+/// 表示一个打开的RocksDB实例
+///
+/// 与进程级单例无关的、可自由克隆的句柄：`open`/`open_with_options`各自返回一个
+/// 独立的`Arc<DbContext>`，因此同一进程里可以同时持有多个互不干扰的数据库
+/// (例如一个缓存库和一个主库)。`initialize`/`get_instance`则在此基础上维护一个
+/// 进程级的默认单例，供不需要多实例的调用方直接使用
 pub struct DbContext {
-    pub db: DB,
+    pub db: TransactionDB,
 }
 
-static INSTANCE: OnceLock<DbContext> = OnceLock::new();
+static DEFAULT_INSTANCE: OnceLock<Arc<DbContext>> = OnceLock::new();
 
 impl DbContext {
-    pub fn initialize(db_path: &Path, column_families: &[&str]) -> Result<()> {
+    /// 打开一个独立的数据库实例
+    ///
+    /// 与`initialize`不同，这里不经过任何进程级单例：调用方可以多次调用`open`，
+    /// 在同一进程中同时持有若干个互相独立的数据库
+    pub fn open(db_path: &Path, column_families: &[&str]) -> Result<Arc<Self>> {
         let db = open_db_with_column_families(db_path, column_families)?;
-        INSTANCE
-            .set(DbContext { db }) // 直接存储DB实例，无需Arc
-            .map_err(|_| anyhow::anyhow!("DbContext already initialized")) // 更新错误消息
+        Ok(Arc::new(DbContext { db }))
+    }
+
+    /// 和`open`类似，但允许为每个列族分别声明选项(目前是TTL、FIFO compaction)，
+    /// 由`ColumnFamily::set_ttl`/`ColumnFamily::set_fifo_max_table_files_size`构造后传入
+    pub fn open_with_options(
+        db_path: &Path,
+        column_families: &[ColumnFamilyConfig],
+    ) -> Result<Arc<Self>> {
+        let db = open_db_with_column_family_configs(db_path, column_families)?;
+        Ok(Arc::new(DbContext { db }))
+    }
+
+    /// 打开默认的进程级单例数据库，供`get_instance()`复用
+    ///
+    /// 只能成功调用一次；需要在同一进程打开多个数据库的调用方应改用`open`
+    pub fn initialize(db_path: &Path, column_families: &[&str]) -> Result<()> {
+        let db = Self::open(db_path, column_families)?;
+        DEFAULT_INSTANCE
+            .set(db)
+            .map_err(|_| anyhow::anyhow!("DbContext already initialized"))
+    }
+
+    /// 和`initialize`类似，但允许为每个列族分别声明选项(目前是TTL、FIFO compaction)
+    pub fn initialize_with_options(
+        db_path: &Path,
+        column_families: &[ColumnFamilyConfig],
+    ) -> Result<()> {
+        let db = Self::open_with_options(db_path, column_families)?;
+        DEFAULT_INSTANCE
+            .set(db)
+            .map_err(|_| anyhow::anyhow!("DbContext already initialized"))
+    }
+
+    /// 获取由`initialize`/`initialize_with_options`打开的默认单例句柄
+    pub fn get_instance() -> Arc<Self> {
+        DEFAULT_INSTANCE
+            .get()
+            .expect("DbContext not initialized")
+            .clone()
+    }
+
+    /// 开启一个新的RocksDB事务，使用默认的`JsonCodec`编解码值
+    ///
+    /// 返回的`Txn`句柄把多次`set`/`del`路由到同一个事务对象上，只有调用
+    /// `commit()`后这些写入才会真正落盘，`rollback()`则会丢弃所有未提交的修改。
+    /// 用于需要跨记录、跨列族保持一致的场景，例如同时更新一个实体及其时间索引记录。
+    /// 如果要在事务里读写一个用`ColumnFamily<T, C>`(非默认编解码器)写入的列族，
+    /// 应改用`transaction_with_codec::<C>()`，否则会用错误的编解码器读写。
+    pub fn transaction(&self) -> Txn<'_> {
+        Txn::new(&self.db)
     }
 
-    pub fn get_instance() -> &'static Self {
-        INSTANCE.get().expect("DbContext not initialized")
+    /// 和`transaction`类似，但显式指定事务所使用的`Codec`，
+    /// 需要和对应列族的`ColumnFamily<T, C>`保持一致
+    pub fn transaction_with_codec<C: Codec>(&self) -> Txn<'_, C> {
+        Txn::new(&self.db)
     }
 }
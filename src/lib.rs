@@ -1,9 +1,21 @@
+mod codec;
+#[cfg(test)]
+mod codec_test;
 mod column_family;
 #[cfg(test)]
 mod column_family_test;
 mod db_context;
+mod ttl;
+mod txn;
+#[cfg(test)]
+mod txn_test;
 mod utils;
 
-pub use column_family::{ColumnFamily, Keyable};
+pub use codec::{BincodeCodec, Codec, JsonCodec};
+pub use column_family::{
+    encode_composite_key, Column, ColumnFamily, ColumnFamilyConfig, ColumnFamilyIter,
+    ColumnFamilyRangeIter, Keyable,
+};
 pub use db_context::DbContext;
+pub use txn::Txn;
 pub use utils::generate_timestamp_index;
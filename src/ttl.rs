@@ -0,0 +1,76 @@
+use rocksdb::compaction_filter::Decision;
+use rocksdb::compaction_filter_factory::{CompactionFilterContext, CompactionFilterFactory};
+use std::ffi::CStr;
+use std::ffi::CString;
+use std::time::Duration;
+
+/// 从`generate_timestamp_index`产生的主键(`"{inverted_timestamp:020}_{config_id}"`)中
+/// 解码出反转时间戳，还原出记录的原始写入时间(单位: 秒)
+fn record_time_from_key(key: &[u8]) -> Option<u64> {
+    let key_str = std::str::from_utf8(key).ok()?;
+    let inverted_timestamp: u64 = key_str.split('_').next()?.parse().ok()?;
+    let max_timestamp = i64::MAX as u64;
+    Some(max_timestamp - inverted_timestamp)
+}
+
+/// 在单次compaction过程中实际执行过期判断的filter
+///
+/// 运行在RocksDB的后台compaction线程上，因此只持有纯数据(TTL以及compaction开始时
+/// 拍下的"当前时间"快照)，绝不会反过来访问DB本身
+pub struct TtlCompactionFilter {
+    ttl: Duration,
+    now: u64,
+}
+
+impl rocksdb::CompactionFilter for TtlCompactionFilter {
+    fn filter(&mut self, _level: u32, key: &[u8], _value: &[u8]) -> Decision {
+        match record_time_from_key(key) {
+            Some(record_time) if self.now.saturating_sub(record_time) > self.ttl.as_secs() => {
+                Decision::Remove
+            }
+            _ => Decision::Keep,
+        }
+    }
+}
+
+/// 按列族注册的TTL compaction filter工厂
+///
+/// 只携带TTL配置与一个用于获取"当前时间"的时钟闭包(均为拥有所有权的纯数据)，
+/// 每次compaction开始时据此构造一个`TtlCompactionFilter`快照
+pub struct TtlCompactionFilterFactory<F: Fn() -> u64 + Send + Sync + 'static> {
+    ttl: Duration,
+    clock: F,
+    name: CString,
+}
+
+impl<F: Fn() -> u64 + Send + Sync + 'static> TtlCompactionFilterFactory<F> {
+    pub fn new(ttl: Duration, clock: F) -> Self {
+        Self {
+            ttl,
+            clock,
+            name: CString::new("ttl_compaction_filter").expect("literal has no interior nul"),
+        }
+    }
+}
+
+impl<F: Fn() -> u64 + Send + Sync + 'static> CompactionFilterFactory
+    for TtlCompactionFilterFactory<F>
+{
+    type Filter = TtlCompactionFilter;
+
+    fn create(&mut self, _context: CompactionFilterContext) -> Self::Filter {
+        TtlCompactionFilter {
+            ttl: self.ttl,
+            now: (self.clock)(),
+        }
+    }
+
+    fn name(&self) -> &CStr {
+        &self.name
+    }
+}
+
+/// 默认时钟：返回当前UTC时间的unix秒数，与`generate_timestamp_index`使用的时间基准一致
+pub fn system_clock() -> u64 {
+    chrono::Utc::now().timestamp() as u64
+}
@@ -0,0 +1,92 @@
+use crate::codec::{Codec, JsonCodec};
+use crate::column_family::Column;
+use anyhow::Context;
+use anyhow::Result;
+use rocksdb::{Transaction, TransactionDB};
+
+/// 基于RocksDB事务的原子写入句柄
+///
+/// 通过`DbContext::transaction()`/`DbContext::transaction_with_codec()`获取，
+/// 把多次`set`/`get`/`del`路由到同一个底层`Transaction`对象上，从而实现跨记录、
+/// 跨列族的原子写入：要么全部生效(`commit`)，要么全部丢弃(`rollback`)。
+/// `cf_handle`的查找方式与`ColumnFamily`保持一致。
+///
+/// 泛型参数`C`是值的编解码器(`Codec`)，默认为`JsonCodec`；如果某个列族是用
+/// `ColumnFamily<T, BincodeCodec>`这类非默认编解码器写入的，必须通过
+/// `transaction_with_codec::<BincodeCodec>()`构造匹配的`Txn`，否则会用错误的
+/// 编解码器读写该列族
+pub struct Txn<'a, C = JsonCodec> {
+    db: &'a TransactionDB,
+    txn: Transaction<'a, TransactionDB>,
+    _codec: std::marker::PhantomData<C>,
+}
+
+impl<'a, C: Codec> Txn<'a, C> {
+    pub(crate) fn new(db: &'a TransactionDB) -> Self {
+        let txn = db.transaction();
+        Self {
+            db,
+            txn,
+            _codec: std::marker::PhantomData,
+        }
+    }
+
+    /// 在事务内查询单条记录
+    pub fn get<T: Column>(&self, key: &T::Key) -> Result<Option<T>> {
+        let cf_handle = self.db.cf_handle(T::column_family()).context(format!(
+            "Failed to get {} column family handle",
+            T::column_family()
+        ))?;
+
+        match self
+            .txn
+            .get_cf(&cf_handle, T::encode_key(key))
+            .context("Failed to read database entry within transaction")?
+        {
+            Some(value) => {
+                let item: T = C::decode(&value)?;
+                Ok(Some(item))
+            }
+            None => Ok(None),
+        }
+    }
+
+    /// 在事务内插入或更新记录
+    pub fn set<T: Column>(&self, item: &T) -> Result<()> {
+        let cf_handle = self.db.cf_handle(T::column_family()).context(format!(
+            "Failed to get {} column family handle",
+            T::column_family()
+        ))?;
+
+        let key = T::encode_key(&Column::key(item));
+        let value = C::encode(item)?;
+
+        self.txn
+            .put_cf(&cf_handle, key, value)
+            .context("Failed to write item within transaction")
+    }
+
+    /// 在事务内删除记录
+    pub fn del<T: Column>(&self, key: &T::Key) -> Result<()> {
+        let cf_handle = self.db.cf_handle(T::column_family()).context(format!(
+            "Failed to get {} column family handle",
+            T::column_family()
+        ))?;
+
+        self.txn
+            .delete_cf(&cf_handle, T::encode_key(key))
+            .context("Failed to delete item within transaction")
+    }
+
+    /// 提交事务，使本事务中的所有写入原子地生效
+    pub fn commit(self) -> Result<()> {
+        self.txn.commit().context("Failed to commit transaction")
+    }
+
+    /// 回滚事务，丢弃本事务中的所有写入
+    pub fn rollback(self) -> Result<()> {
+        self.txn
+            .rollback()
+            .context("Failed to roll back transaction")
+    }
+}
@@ -0,0 +1,94 @@
+use crate::{ColumnFamily, DbContext, Keyable};
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use tempfile::TempDir;
+
+#[derive(Serialize, Deserialize, PartialEq, Eq, Debug, Clone)]
+struct TxnEntity {
+    id: String,
+    value: String,
+}
+
+impl Keyable for TxnEntity {
+    fn key(&self) -> String {
+        self.id.clone()
+    }
+
+    fn column_family() -> &'static str {
+        "txn_entities"
+    }
+}
+
+#[derive(Serialize, Deserialize, PartialEq, Eq, Debug, Clone)]
+struct TxnTimeIndex {
+    key: String,
+    entity_id: String,
+}
+
+impl Keyable for TxnTimeIndex {
+    fn key(&self) -> String {
+        self.key.clone()
+    }
+
+    fn column_family() -> &'static str {
+        "txn_time_index"
+    }
+}
+
+#[test]
+fn test_commit_persists_writes_across_column_families() -> Result<()> {
+    let temp_dir = TempDir::new().expect("Failed to create temp directory");
+    let column_families = vec!["txn_entities", "txn_time_index"];
+    let db = DbContext::open(temp_dir.path(), &column_families)?;
+
+    let entity_cf = ColumnFamily::<TxnEntity>::new(db.clone());
+    let index_cf = ColumnFamily::<TxnTimeIndex>::new(db.clone());
+
+    let txn = db.transaction();
+    txn.set(&TxnEntity {
+        id: "e1".to_string(),
+        value: "v1".to_string(),
+    })?;
+    txn.set(&TxnTimeIndex {
+        key: "idx1".to_string(),
+        entity_id: "e1".to_string(),
+    })?;
+    txn.commit()?;
+
+    assert_eq!(
+        entity_cf.get(&"e1".to_string())?.unwrap().value,
+        "v1".to_string()
+    );
+    assert_eq!(
+        index_cf.get(&"idx1".to_string())?.unwrap().entity_id,
+        "e1".to_string()
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_rollback_discards_writes_across_column_families() -> Result<()> {
+    let temp_dir = TempDir::new().expect("Failed to create temp directory");
+    let column_families = vec!["txn_entities", "txn_time_index"];
+    let db = DbContext::open(temp_dir.path(), &column_families)?;
+
+    let entity_cf = ColumnFamily::<TxnEntity>::new(db.clone());
+    let index_cf = ColumnFamily::<TxnTimeIndex>::new(db.clone());
+
+    let txn = db.transaction();
+    txn.set(&TxnEntity {
+        id: "e2".to_string(),
+        value: "v2".to_string(),
+    })?;
+    txn.set(&TxnTimeIndex {
+        key: "idx2".to_string(),
+        entity_id: "e2".to_string(),
+    })?;
+    txn.rollback()?;
+
+    assert!(entity_cf.get(&"e2".to_string())?.is_none());
+    assert!(index_cf.get(&"idx2".to_string())?.is_none());
+
+    Ok(())
+}